@@ -0,0 +1,321 @@
+//! Generic hook management and matcher evaluation
+//!
+//! `ensure_transcript_hook` only ever manages one hardcoded Stop hook. This
+//! module generalizes that to every event: callers can add, remove, and
+//! list hooks for any `HookEvent`, and [`Hooks::hooks_for`] simulates which
+//! hooks Claude Code would actually fire for a given event (and, for tool
+//! events, a given tool name), matching both exact tool names and regex
+//! patterns like `Edit|Write` or `Notebook.*`.
+
+use super::{HookAction, HookMatcher, Hooks, MatcherConfig};
+use regex::Regex;
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+
+/// The Claude Code lifecycle events that carry hooks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum HookEvent {
+    Stop,
+    PreToolUse,
+    PostToolUse,
+    UserPromptSubmit,
+    PreCompact,
+    SubagentStop,
+}
+
+impl HookEvent {
+    /// Whether this event fires per-tool-invocation (and therefore matches
+    /// against a tool name) as opposed to once per turn/session.
+    fn is_tool_event(self) -> bool {
+        matches!(self, HookEvent::PreToolUse | HookEvent::PostToolUse)
+    }
+
+    fn field(self, hooks: &Hooks) -> &Option<Vec<HookMatcher>> {
+        match self {
+            HookEvent::Stop => &hooks.stop,
+            HookEvent::PreToolUse => &hooks.pre_tool_use,
+            HookEvent::PostToolUse => &hooks.post_tool_use,
+            HookEvent::UserPromptSubmit => &hooks.user_prompt_submit,
+            HookEvent::PreCompact => &hooks.pre_compact,
+            HookEvent::SubagentStop => &hooks.subagent_stop,
+        }
+    }
+
+    fn field_mut(self, hooks: &mut Hooks) -> &mut Option<Vec<HookMatcher>> {
+        match self {
+            HookEvent::Stop => &mut hooks.stop,
+            HookEvent::PreToolUse => &mut hooks.pre_tool_use,
+            HookEvent::PostToolUse => &mut hooks.post_tool_use,
+            HookEvent::UserPromptSubmit => &mut hooks.user_prompt_submit,
+            HookEvent::PreCompact => &mut hooks.pre_compact,
+            HookEvent::SubagentStop => &mut hooks.subagent_stop,
+        }
+    }
+}
+
+/// Compiled regex patterns, keyed by source pattern, so repeated calls to
+/// `hooks_for` don't recompile the same pattern on every event.
+fn pattern_cache() -> &'static Mutex<HashMap<String, Regex>> {
+    static CACHE: OnceLock<Mutex<HashMap<String, Regex>>> = OnceLock::new();
+    CACHE.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// Whether `pattern` matches `tool_name`, treating `pattern` as a regex
+/// (compiling and caching it on first use) and falling back to an exact
+/// match if it fails to compile.
+fn pattern_matches(pattern: &str, tool_name: &str) -> bool {
+    let mut cache = pattern_cache().lock().unwrap_or_else(|e| e.into_inner());
+    if let Some(re) = cache.get(pattern) {
+        return re.is_match(tool_name);
+    }
+    match Regex::new(&format!("^(?:{pattern})$")) {
+        Ok(re) => {
+            let matched = re.is_match(tool_name);
+            cache.insert(pattern.to_string(), re);
+            matched
+        }
+        Err(_) => pattern == tool_name,
+    }
+}
+
+impl Hooks {
+    /// Add `action` under `matcher` for `event`. If a matcher with the same
+    /// config already exists for this event, the action is appended to it;
+    /// otherwise a new matcher is pushed.
+    pub fn add_hook(&mut self, event: HookEvent, matcher: MatcherConfig, action: HookAction) {
+        let matchers = event.field_mut(self).get_or_insert_with(Vec::new);
+        if let Some(existing) = matchers.iter_mut().find(|m| m.matcher == matcher) {
+            existing.hooks.push(action);
+        } else {
+            matchers.push(HookMatcher {
+                matcher,
+                hooks: vec![action],
+            });
+        }
+    }
+
+    /// Remove every hook action matching `predicate` from `event`, dropping
+    /// any matcher left with no actions.
+    pub fn remove_hooks_matching(&mut self, event: HookEvent, predicate: impl Fn(&HookAction) -> bool) {
+        let Some(matchers) = event.field_mut(self).as_mut() else {
+            return;
+        };
+        for matcher in matchers.iter_mut() {
+            matcher.hooks.retain(|action| !predicate(action));
+        }
+        matchers.retain(|m| !m.hooks.is_empty());
+    }
+
+    /// All hook actions registered for `event`, across every matcher.
+    pub fn list_hooks(&self, event: HookEvent) -> Vec<&HookAction> {
+        event
+            .field(self)
+            .iter()
+            .flatten()
+            .flat_map(|m| m.hooks.iter())
+            .collect()
+    }
+
+    /// The hook actions that Claude Code would actually run for `event`,
+    /// given (for tool events) the name of the invoked tool.
+    ///
+    /// A matcher with no `tools` list matches every invocation of the
+    /// event. Otherwise each entry in `tools` is checked against
+    /// `tool_name` as a regex (so exact names and patterns like
+    /// `Edit|Write` or `Notebook.*` both work).
+    pub fn hooks_for(&self, event: HookEvent, tool_name: Option<&str>) -> Vec<&HookAction> {
+        let Some(matchers) = event.field(self) else {
+            return Vec::new();
+        };
+
+        matchers
+            .iter()
+            .filter(|m| match &m.matcher.tools {
+                None => true,
+                Some(patterns) => {
+                    event.is_tool_event()
+                        && tool_name.is_some_and(|name| {
+                            patterns.iter().any(|pattern| pattern_matches(pattern, name))
+                        })
+                }
+            })
+            .flat_map(|m| m.hooks.iter())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tools_matcher(tools: &[&str]) -> MatcherConfig {
+        MatcherConfig {
+            tools: Some(tools.iter().map(|s| s.to_string()).collect()),
+            extra: HashMap::new(),
+        }
+    }
+
+    #[test]
+    fn add_hook_groups_into_existing_matcher_with_same_config() {
+        let mut hooks = Hooks::default();
+        let matcher = tools_matcher(&["Bash"]);
+
+        hooks.add_hook(HookEvent::PreToolUse, matcher.clone(), HookAction::command("echo 1"));
+        hooks.add_hook(HookEvent::PreToolUse, matcher, HookAction::command("echo 2"));
+
+        let pre_tool_use = hooks.pre_tool_use.unwrap();
+        assert_eq!(pre_tool_use.len(), 1);
+        assert_eq!(pre_tool_use[0].hooks.len(), 2);
+    }
+
+    #[test]
+    fn add_hook_creates_new_matcher_for_different_config() {
+        let mut hooks = Hooks::default();
+
+        hooks.add_hook(
+            HookEvent::PreToolUse,
+            tools_matcher(&["Bash"]),
+            HookAction::command("echo bash"),
+        );
+        hooks.add_hook(
+            HookEvent::PreToolUse,
+            tools_matcher(&["Read"]),
+            HookAction::command("echo read"),
+        );
+
+        assert_eq!(hooks.pre_tool_use.unwrap().len(), 2);
+    }
+
+    #[test]
+    fn remove_hooks_matching_drops_empty_matchers() {
+        let mut hooks = Hooks::default();
+        hooks.add_hook(
+            HookEvent::Stop,
+            MatcherConfig::default(),
+            HookAction::command("echo keep"),
+        );
+        hooks.add_hook(
+            HookEvent::Stop,
+            MatcherConfig::default(),
+            HookAction::command("echo drop"),
+        );
+
+        hooks.remove_hooks_matching(HookEvent::Stop, |action| action.command.contains("drop"));
+
+        let remaining = hooks.list_hooks(HookEvent::Stop);
+        assert_eq!(remaining.len(), 1);
+        assert_eq!(remaining[0].command, "echo keep");
+    }
+
+    #[test]
+    fn remove_hooks_matching_removes_matcher_when_last_action_dropped() {
+        let mut hooks = Hooks::default();
+        hooks.add_hook(
+            HookEvent::Stop,
+            MatcherConfig::default(),
+            HookAction::command("echo only"),
+        );
+
+        hooks.remove_hooks_matching(HookEvent::Stop, |_| true);
+
+        assert!(hooks.stop.unwrap().is_empty());
+    }
+
+    #[test]
+    fn list_hooks_is_empty_for_unset_event() {
+        let hooks = Hooks::default();
+        assert!(hooks.list_hooks(HookEvent::PreCompact).is_empty());
+    }
+
+    #[test]
+    fn hooks_for_matches_untargeted_matcher_regardless_of_tool() {
+        let mut hooks = Hooks::default();
+        hooks.add_hook(
+            HookEvent::Stop,
+            MatcherConfig::default(),
+            HookAction::command("echo stop"),
+        );
+
+        assert_eq!(hooks.hooks_for(HookEvent::Stop, None).len(), 1);
+    }
+
+    #[test]
+    fn hooks_for_matches_exact_tool_name() {
+        let mut hooks = Hooks::default();
+        hooks.add_hook(
+            HookEvent::PreToolUse,
+            tools_matcher(&["Bash"]),
+            HookAction::command("echo bash-guard"),
+        );
+
+        assert_eq!(hooks.hooks_for(HookEvent::PreToolUse, Some("Bash")).len(), 1);
+        assert!(hooks.hooks_for(HookEvent::PreToolUse, Some("Read")).is_empty());
+    }
+
+    #[test]
+    fn hooks_for_matches_regex_pattern() {
+        let mut hooks = Hooks::default();
+        hooks.add_hook(
+            HookEvent::PostToolUse,
+            tools_matcher(&["Edit|Write"]),
+            HookAction::command("echo edit-or-write"),
+        );
+
+        assert_eq!(hooks.hooks_for(HookEvent::PostToolUse, Some("Edit")).len(), 1);
+        assert_eq!(hooks.hooks_for(HookEvent::PostToolUse, Some("Write")).len(), 1);
+        assert!(hooks
+            .hooks_for(HookEvent::PostToolUse, Some("Bash"))
+            .is_empty());
+    }
+
+    #[test]
+    fn hooks_for_matches_wildcard_regex_pattern() {
+        let mut hooks = Hooks::default();
+        hooks.add_hook(
+            HookEvent::PreToolUse,
+            tools_matcher(&["Notebook.*"]),
+            HookAction::command("echo notebook"),
+        );
+
+        assert_eq!(
+            hooks
+                .hooks_for(HookEvent::PreToolUse, Some("NotebookEdit"))
+                .len(),
+            1
+        );
+    }
+
+    #[test]
+    fn hooks_for_caches_compiled_pattern_across_calls() {
+        let mut hooks = Hooks::default();
+        hooks.add_hook(
+            HookEvent::PreToolUse,
+            tools_matcher(&["Uniq_Pattern_For_Cache_Test.*"]),
+            HookAction::command("echo cached"),
+        );
+
+        assert!(!hooks
+            .hooks_for(HookEvent::PreToolUse, Some("Uniq_Pattern_For_Cache_TestX"))
+            .is_empty());
+
+        let cache = pattern_cache().lock().unwrap();
+        assert!(cache.contains_key("Uniq_Pattern_For_Cache_Test.*"));
+    }
+
+    #[test]
+    fn hooks_for_falls_back_to_exact_match_on_invalid_regex() {
+        let mut hooks = Hooks::default();
+        hooks.add_hook(
+            HookEvent::PreToolUse,
+            tools_matcher(&["Bash(unterminated"),
+            HookAction::command("echo literal"),
+        );
+
+        assert_eq!(
+            hooks
+                .hooks_for(HookEvent::PreToolUse, Some("Bash(unterminated"))
+                .len(),
+            1
+        );
+    }
+}
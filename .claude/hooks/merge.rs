@@ -0,0 +1,357 @@
+//! Layered merge of Claude Code settings
+//!
+//! Claude Code reads settings from several locations with ascending
+//! precedence: enterprise managed policy, the user's `~/.claude/settings.json`,
+//! the project's `.claude/settings.json`, and finally
+//! `.claude/settings.local.json`. [`ClaudeSettings::resolve_layered`] loads
+//! whichever of these files exist and folds them together with the
+//! [`Merge`] trait so the effective settings match what Claude Code itself
+//! would see.
+
+use super::{ClaudeSettings, HookEventAccessor, HookMatcher, Hooks, Permissions};
+use crate::error::Result;
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+/// A settings source, in ascending precedence order (later layers win).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Layer {
+    Enterprise,
+    User,
+    Project,
+    Local,
+}
+
+/// Merge a higher-precedence value on top of `self`.
+pub trait Merge {
+    fn merge(self, higher: Self) -> Self;
+}
+
+impl Merge for ClaudeSettings {
+    fn merge(self, higher: Self) -> Self {
+        Self {
+            permissions: merge_option(self.permissions, higher.permissions, Merge::merge),
+            hooks: merge_option(self.hooks, higher.hooks, Merge::merge),
+            enable_all_project_mcp_servers: higher
+                .enable_all_project_mcp_servers
+                .or(self.enable_all_project_mcp_servers),
+            extra: merge_extra(self.extra, higher.extra),
+        }
+    }
+}
+
+impl Merge for Permissions {
+    fn merge(self, higher: Self) -> Self {
+        Self {
+            allow: merge_dedup(self.allow, higher.allow),
+            deny: merge_dedup(self.deny, higher.deny),
+            ask: merge_dedup(self.ask, higher.ask),
+            extra: merge_extra(self.extra, higher.extra),
+        }
+    }
+}
+
+impl Merge for Hooks {
+    fn merge(self, higher: Self) -> Self {
+        Self {
+            stop: merge_matchers(self.stop, higher.stop),
+            pre_tool_use: merge_matchers(self.pre_tool_use, higher.pre_tool_use),
+            post_tool_use: merge_matchers(self.post_tool_use, higher.post_tool_use),
+            user_prompt_submit: merge_matchers(self.user_prompt_submit, higher.user_prompt_submit),
+            pre_compact: merge_matchers(self.pre_compact, higher.pre_compact),
+            subagent_stop: merge_matchers(self.subagent_stop, higher.subagent_stop),
+            extra: merge_extra(self.extra, higher.extra),
+        }
+    }
+}
+
+fn merge_option<T>(base: Option<T>, higher: Option<T>, merge: impl FnOnce(T, T) -> T) -> Option<T> {
+    match (base, higher) {
+        (Some(a), Some(b)) => Some(merge(a, b)),
+        (a, None) => a,
+        (None, b) => b,
+    }
+}
+
+fn merge_dedup(base: Option<Vec<String>>, higher: Option<Vec<String>>) -> Option<Vec<String>> {
+    match (base, higher) {
+        (None, None) => None,
+        (Some(v), None) | (None, Some(v)) => Some(v),
+        (Some(mut a), Some(b)) => {
+            for item in b {
+                if !a.contains(&item) {
+                    a.push(item);
+                }
+            }
+            Some(a)
+        }
+    }
+}
+
+fn merge_extra(
+    base: HashMap<String, serde_json::Value>,
+    higher: HashMap<String, serde_json::Value>,
+) -> HashMap<String, serde_json::Value> {
+    let mut merged = base;
+    merged.extend(higher);
+    merged
+}
+
+/// Commands of a matcher's hooks, sorted, used (together with
+/// `matcher_tools`) to decide whether two `HookMatcher`s are "the same hook"
+/// for merge/coalesce purposes.
+fn matcher_commands(matcher: &HookMatcher) -> Vec<String> {
+    let mut commands: Vec<String> = matcher.hooks.iter().map(|h| h.command.clone()).collect();
+    commands.sort();
+    commands
+}
+
+/// The matcher's `tools` scope, sorted for order-independent comparison.
+fn matcher_tools(matcher: &HookMatcher) -> Option<Vec<String>> {
+    matcher.matcher.tools.as_ref().map(|tools| {
+        let mut tools = tools.clone();
+        tools.sort();
+        tools
+    })
+}
+
+/// Two matchers are the same hook only if both their commands *and* their
+/// `tools` scope agree — the same script guarded by different tool filters
+/// is a distinct hook, not a duplicate.
+fn same_hook(a: &HookMatcher, b: &HookMatcher) -> bool {
+    matcher_commands(a) == matcher_commands(b) && matcher_tools(a) == matcher_tools(b)
+}
+
+/// Concatenate two matcher lists, coalescing matchers that are `same_hook`
+/// so that merging the same layer twice is idempotent.
+fn merge_matchers(
+    base: Option<Vec<HookMatcher>>,
+    higher: Option<Vec<HookMatcher>>,
+) -> Option<Vec<HookMatcher>> {
+    match (base, higher) {
+        (None, None) => None,
+        (Some(v), None) | (None, Some(v)) => Some(v),
+        (Some(mut combined), Some(added)) => {
+            for matcher in added {
+                if !combined.iter().any(|m| same_hook(m, &matcher)) {
+                    combined.push(matcher);
+                }
+            }
+            Some(combined)
+        }
+    }
+}
+
+/// The six Claude Code hook events, paired with an accessor, so origin
+/// tracking doesn't need to special-case each field by hand.
+const HOOK_EVENTS: &[(&str, HookEventAccessor)] = &[
+    ("Stop", |h| &h.stop),
+    ("PreToolUse", |h| &h.pre_tool_use),
+    ("PostToolUse", |h| &h.post_tool_use),
+    ("UserPromptSubmit", |h| &h.user_prompt_submit),
+    ("PreCompact", |h| &h.pre_compact),
+    ("SubagentStop", |h| &h.subagent_stop),
+];
+
+/// Identifies a single merged hook matcher by the event it fires on, its
+/// command set, and its `tools` scope — the same fields `same_hook` uses to
+/// decide matcher identity, so two matchers that coalesce share one key and
+/// two that don't stay distinct.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct HookOriginKey {
+    pub event: &'static str,
+    pub commands: Vec<String>,
+    pub tools: Option<Vec<String>>,
+}
+
+/// The result of [`ClaudeSettings::resolve_layered`]: the folded settings
+/// plus, for every hook matcher present in the result, the lowest layer it
+/// was first defined in. Callers that want to write back only project-level
+/// changes can filter `hook_origins` for `Layer::Project`/`Layer::Local`.
+#[derive(Debug, Clone)]
+pub struct ResolvedSettings {
+    pub settings: ClaudeSettings,
+    pub hook_origins: HashMap<HookOriginKey, Layer>,
+}
+
+fn record_hook_origins(hooks: &Hooks, layer: Layer, origins: &mut HashMap<HookOriginKey, Layer>) {
+    for (event, accessor) in HOOK_EVENTS {
+        let Some(matchers) = accessor(hooks) else {
+            continue;
+        };
+        for matcher in matchers {
+            let key = HookOriginKey {
+                event,
+                commands: matcher_commands(matcher),
+                tools: matcher_tools(matcher),
+            };
+            // The first (lowest-precedence) layer to define a hook is the one
+            // that "owns" it, even if a higher layer re-lists the same hook.
+            origins.entry(key).or_insert(layer);
+        }
+    }
+}
+
+impl ClaudeSettings {
+    /// Load each settings file that exists, in ascending precedence order,
+    /// and fold them into one effective `ClaudeSettings` using [`Merge`].
+    pub fn resolve_layered(paths: &[(PathBuf, Layer)]) -> Result<ResolvedSettings> {
+        let mut settings = ClaudeSettings::default();
+        let mut hook_origins = HashMap::new();
+
+        for (path, layer) in paths {
+            if !path.exists() {
+                continue;
+            }
+            let layer_settings = Self::load_from_path(path)?;
+            if let Some(hooks) = &layer_settings.hooks {
+                record_hook_origins(hooks, *layer, &mut hook_origins);
+            }
+            settings = settings.merge(layer_settings);
+        }
+
+        Ok(ResolvedSettings {
+            settings,
+            hook_origins,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hooks::{HookAction, MatcherConfig};
+    use std::fs;
+    use tempfile::TempDir;
+
+    fn matcher(tools: Option<&[&str]>, command: &str) -> HookMatcher {
+        HookMatcher {
+            matcher: MatcherConfig {
+                tools: tools.map(|t| t.iter().map(|s| s.to_string()).collect()),
+                extra: HashMap::new(),
+            },
+            hooks: vec![HookAction::command(command)],
+        }
+    }
+
+    #[test]
+    fn merge_permissions_dedups_allow_list() {
+        let base = Permissions {
+            allow: Some(vec!["Bash(git commit:*)".to_string()]),
+            deny: None,
+            ask: None,
+            extra: HashMap::new(),
+        };
+        let higher = Permissions {
+            allow: Some(vec![
+                "Bash(git commit:*)".to_string(),
+                "Read(~/.ssh/**)".to_string(),
+            ]),
+            deny: None,
+            ask: None,
+            extra: HashMap::new(),
+        };
+
+        let merged = base.merge(higher);
+        assert_eq!(
+            merged.allow.unwrap(),
+            vec![
+                "Bash(git commit:*)".to_string(),
+                "Read(~/.ssh/**)".to_string()
+            ]
+        );
+    }
+
+    #[test]
+    fn merge_hooks_same_command_and_tools_coalesces() {
+        let base = Hooks {
+            pre_tool_use: Some(vec![matcher(Some(&["Bash"]), "shared-script.sh")]),
+            ..Hooks::default()
+        };
+        let higher = Hooks {
+            pre_tool_use: Some(vec![matcher(Some(&["Bash"]), "shared-script.sh")]),
+            ..Hooks::default()
+        };
+
+        let merged = base.merge(higher);
+        assert_eq!(merged.pre_tool_use.unwrap().len(), 1);
+    }
+
+    #[test]
+    fn merge_hooks_same_command_different_tools_stays_distinct() {
+        // Same script, but scoped to different tools at each layer: both
+        // matchers must survive the merge since they fire in different
+        // circumstances.
+        let base = Hooks {
+            pre_tool_use: Some(vec![matcher(Some(&["Bash"]), "shared-script.sh")]),
+            ..Hooks::default()
+        };
+        let higher = Hooks {
+            pre_tool_use: Some(vec![matcher(Some(&["Read"]), "shared-script.sh")]),
+            ..Hooks::default()
+        };
+
+        let merged = base.merge(higher);
+        let pre_tool_use = merged.pre_tool_use.unwrap();
+        assert_eq!(pre_tool_use.len(), 2);
+    }
+
+    #[test]
+    fn merge_is_idempotent_when_reapplying_same_layer() {
+        let layer = Hooks {
+            stop: Some(vec![matcher(None, "echo hi")]),
+            ..Hooks::default()
+        };
+
+        let once = Hooks::default().merge(layer.clone());
+        let twice = once.clone().merge(layer);
+        assert_eq!(twice.stop.unwrap().len(), once.stop.unwrap().len());
+    }
+
+    #[test]
+    fn resolve_layered_tracks_lowest_layer_as_origin() {
+        let temp = TempDir::new().unwrap();
+        let user_path = temp.path().join("user.json");
+        let project_path = temp.path().join("project.json");
+
+        fs::write(
+            &user_path,
+            r#"{"hooks": {"Stop": [{"matcher": {}, "hooks": [{"type": "command", "command": "echo user"}]}]}}"#,
+        )
+        .unwrap();
+        fs::write(
+            &project_path,
+            r#"{"hooks": {"Stop": [{"matcher": {}, "hooks": [{"type": "command", "command": "echo user"}]}]}}"#,
+        )
+        .unwrap();
+
+        let resolved = ClaudeSettings::resolve_layered(&[
+            (user_path, Layer::User),
+            (project_path, Layer::Project),
+        ])
+        .unwrap();
+
+        assert_eq!(resolved.settings.hooks.unwrap().stop.unwrap().len(), 1);
+        let origin = resolved
+            .hook_origins
+            .get(&HookOriginKey {
+                event: "Stop",
+                commands: vec!["echo user".to_string()],
+                tools: None,
+            })
+            .copied();
+        assert_eq!(origin, Some(Layer::User));
+    }
+
+    #[test]
+    fn resolve_layered_skips_missing_files() {
+        let temp = TempDir::new().unwrap();
+        let missing_path = temp.path().join("nonexistent.json");
+
+        let resolved =
+            ClaudeSettings::resolve_layered(&[(missing_path, Layer::Enterprise)]).unwrap();
+
+        assert!(resolved.settings.hooks.is_none());
+        assert!(resolved.hook_origins.is_empty());
+    }
+}
@@ -22,6 +22,18 @@ use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::path::Path;
 
+mod events;
+mod merge;
+mod permissions;
+mod persist;
+mod validate;
+
+pub use events::HookEvent;
+pub use merge::{HookOriginKey, Layer, Merge, ResolvedSettings};
+pub use permissions::{AddRuleOutcome, PermissionRule, RuleKind};
+pub use persist::SettingsGuard;
+pub use validate::ValidationError;
+
 /// Claude Code settings.json structure
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 #[serde(rename_all = "camelCase")]
@@ -41,10 +53,19 @@ pub struct ClaudeSettings {
 pub struct Permissions {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub allow: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub deny: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ask: Option<Vec<String>>,
     #[serde(flatten)]
     pub extra: HashMap<String, serde_json::Value>,
 }
 
+/// Accessor for a single hook event's matcher list, used to iterate all six
+/// events table-driven instead of hand-writing matching arms in every place
+/// that needs to walk them.
+pub(crate) type HookEventAccessor = fn(&Hooks) -> &Option<Vec<HookMatcher>>;
+
 /// Hook configurations for Claude Code SDK events
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 #[serde(rename_all = "PascalCase")]
@@ -78,7 +99,7 @@ pub struct HookMatcher {
 }
 
 /// Matcher configuration for filtering which events trigger hooks
-#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
 pub struct MatcherConfig {
     /// For tool hooks, filter by tool names
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -188,14 +209,12 @@ impl ClaudeSettings {
 
     /// Save settings to a file path
     ///
-    /// Creates parent directories if they don't exist.
+    /// Creates parent directories if they don't exist. The write is atomic:
+    /// it lands in a sibling temp file first and is renamed over `path`, so
+    /// a concurrent reader never observes a partial file.
     pub fn save_to_path(&self, path: &Path) -> Result<()> {
-        if let Some(parent) = path.parent() {
-            std::fs::create_dir_all(parent)?;
-        }
         let content = serde_json::to_string_pretty(self)?;
-        std::fs::write(path, content)?;
-        Ok(())
+        persist::atomic_write(path, &content)
     }
 }
 
@@ -0,0 +1,194 @@
+//! Permission rule CRUD
+//!
+//! `Permissions` stores `allow`/`deny`/`ask` rule lists as raw strings like
+//! `Bash(git commit:*)` or `Read(~/.ssh/**)`. This module parses those
+//! strings into a `PermissionRule { tool, specifier }` so rules can be
+//! added, removed, and queried by semantic equality rather than raw string
+//! matching, mirroring the `permission new/add/rm/ls` surface of the
+//! Claude Code CLI.
+
+use super::Permissions;
+
+/// Which of the three rule lists a rule belongs to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RuleKind {
+    Allow,
+    Deny,
+    Ask,
+}
+
+/// A permission rule parsed into its tool and specifier, e.g.
+/// `Bash(git commit:*)` -> tool `"Bash"`, specifier `Some("git commit:*")`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PermissionRule {
+    pub tool: String,
+    pub specifier: Option<String>,
+}
+
+impl PermissionRule {
+    /// Parse a rule string of the form `Tool(specifier)`, or a bare `Tool`.
+    pub fn parse(rule: &str) -> Self {
+        match rule.find('(') {
+            Some(open) if rule.ends_with(')') => Self {
+                tool: rule[..open].to_string(),
+                specifier: Some(rule[open + 1..rule.len() - 1].to_string()),
+            },
+            _ => Self {
+                tool: rule.to_string(),
+                specifier: None,
+            },
+        }
+    }
+}
+
+/// Outcome of [`Permissions::add_rule`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AddRuleOutcome {
+    /// The rule was appended to `kind`'s list.
+    Added,
+    /// A semantically-equal rule already existed in `kind`'s list.
+    AlreadyPresent,
+    /// The rule was added, but a semantically-equal rule already exists in
+    /// the returned (different) list, which likely indicates a conflict
+    /// (e.g. the same rule in both `allow` and `deny`).
+    ConflictsWith(RuleKind),
+}
+
+impl Permissions {
+    fn list(&self, kind: RuleKind) -> &[String] {
+        let list = match kind {
+            RuleKind::Allow => &self.allow,
+            RuleKind::Deny => &self.deny,
+            RuleKind::Ask => &self.ask,
+        };
+        list.as_deref().unwrap_or(&[])
+    }
+
+    fn list_mut(&mut self, kind: RuleKind) -> &mut Vec<String> {
+        let list = match kind {
+            RuleKind::Allow => &mut self.allow,
+            RuleKind::Deny => &mut self.deny,
+            RuleKind::Ask => &mut self.ask,
+        };
+        list.get_or_insert_with(Vec::new)
+    }
+
+    /// The raw rule strings currently in `kind`'s list.
+    pub fn list_rules(&self, kind: RuleKind) -> &[String] {
+        self.list(kind)
+    }
+
+    /// Whether a semantically-equal rule already exists in `kind`'s list.
+    pub fn has_rule(&self, kind: RuleKind, rule: &str) -> bool {
+        let parsed = PermissionRule::parse(rule);
+        self.list(kind)
+            .iter()
+            .any(|existing| PermissionRule::parse(existing) == parsed)
+    }
+
+    /// Add `rule` to `kind`'s list, deduplicating by semantic equality. If
+    /// the identical rule already exists in one of the other two lists,
+    /// the rule is still added but `ConflictsWith` is returned so callers
+    /// can warn the user.
+    pub fn add_rule(&mut self, kind: RuleKind, rule: &str) -> AddRuleOutcome {
+        if self.has_rule(kind, rule) {
+            return AddRuleOutcome::AlreadyPresent;
+        }
+
+        let conflict = [RuleKind::Allow, RuleKind::Deny, RuleKind::Ask]
+            .into_iter()
+            .find(|&other| other != kind && self.has_rule(other, rule));
+
+        self.list_mut(kind).push(rule.to_string());
+
+        match conflict {
+            Some(other) => AddRuleOutcome::ConflictsWith(other),
+            None => AddRuleOutcome::Added,
+        }
+    }
+
+    /// Remove any rule semantically equal to `rule` from `kind`'s list.
+    pub fn remove_rule(&mut self, kind: RuleKind, rule: &str) {
+        let parsed = PermissionRule::parse(rule);
+        if let Some(list) = match kind {
+            RuleKind::Allow => self.allow.as_mut(),
+            RuleKind::Deny => self.deny.as_mut(),
+            RuleKind::Ask => self.ask.as_mut(),
+        } {
+            list.retain(|existing| PermissionRule::parse(existing) != parsed);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_rule_with_specifier() {
+        let rule = PermissionRule::parse("Bash(git commit:*)");
+        assert_eq!(rule.tool, "Bash");
+        assert_eq!(rule.specifier.as_deref(), Some("git commit:*"));
+    }
+
+    #[test]
+    fn parse_bare_rule_without_specifier() {
+        let rule = PermissionRule::parse("WebSearch");
+        assert_eq!(rule.tool, "WebSearch");
+        assert_eq!(rule.specifier, None);
+    }
+
+    #[test]
+    fn add_rule_appends_to_requested_list() {
+        let mut permissions = Permissions::default();
+
+        let outcome = permissions.add_rule(RuleKind::Allow, "Bash(git commit:*)");
+
+        assert_eq!(outcome, AddRuleOutcome::Added);
+        assert_eq!(
+            permissions.list_rules(RuleKind::Allow),
+            ["Bash(git commit:*)"]
+        );
+    }
+
+    #[test]
+    fn add_rule_dedups_by_semantic_equality_not_raw_string() {
+        let mut permissions = Permissions::default();
+        permissions.add_rule(RuleKind::Allow, "Bash(git commit:*)");
+
+        // Same tool + specifier, different raw spacing — still a duplicate.
+        let outcome = permissions.add_rule(RuleKind::Allow, "Bash(git commit:*)");
+
+        assert_eq!(outcome, AddRuleOutcome::AlreadyPresent);
+        assert_eq!(permissions.list_rules(RuleKind::Allow).len(), 1);
+    }
+
+    #[test]
+    fn add_rule_flags_conflict_with_other_list() {
+        let mut permissions = Permissions::default();
+        permissions.add_rule(RuleKind::Allow, "Read(~/.ssh/**)");
+
+        let outcome = permissions.add_rule(RuleKind::Deny, "Read(~/.ssh/**)");
+
+        assert_eq!(outcome, AddRuleOutcome::ConflictsWith(RuleKind::Allow));
+        // The rule is still added to the requested list despite the conflict.
+        assert!(permissions.has_rule(RuleKind::Deny, "Read(~/.ssh/**)"));
+    }
+
+    #[test]
+    fn remove_rule_drops_semantically_equal_entry() {
+        let mut permissions = Permissions::default();
+        permissions.add_rule(RuleKind::Ask, "Bash(npm publish:*)");
+
+        permissions.remove_rule(RuleKind::Ask, "Bash(npm publish:*)");
+
+        assert!(permissions.list_rules(RuleKind::Ask).is_empty());
+    }
+
+    #[test]
+    fn remove_rule_on_empty_list_is_a_no_op() {
+        let mut permissions = Permissions::default();
+        permissions.remove_rule(RuleKind::Allow, "Bash(anything)");
+        assert!(permissions.list_rules(RuleKind::Allow).is_empty());
+    }
+}
@@ -0,0 +1,241 @@
+//! Atomic, lock-guarded settings persistence
+//!
+//! `save_to_path` used to `std::fs::write` the destination directly, which
+//! can leave a half-written, unparseable `settings.json` if it races
+//! another Claude Code (or silmari) process writing the same file. Writes
+//! now go to a sibling temp file, `fsync`, then `rename` over the
+//! destination so readers only ever observe a complete file, and
+//! [`ClaudeSettings::update_in_place`] wraps a load-modify-save cycle in an
+//! advisory lock so two processes never clobber each other's edit.
+
+use crate::error::{Result, SilmariError};
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Read as _, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+const LOCK_POLL_INTERVAL: Duration = Duration::from_millis(25);
+/// How long `acquire` waits for a concurrent holder before giving up.
+const LOCK_WAIT_TIMEOUT: Duration = Duration::from_secs(5);
+/// How old an unreleased lock file has to be before it's assumed abandoned
+/// by a crashed process. Deliberately much larger than `LOCK_WAIT_TIMEOUT`
+/// so a legitimately slow (but alive) holder never has its lock stolen out
+/// from under it.
+const STALE_LOCK_AGE: Duration = Duration::from_secs(30);
+
+/// Serialize-to-temp-file-then-rename so `path` is never observed in a
+/// partially-written state.
+pub(super) fn atomic_write(path: &Path, contents: &str) -> Result<()> {
+    let parent = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    fs::create_dir_all(parent)?;
+
+    let tmp_name = format!(
+        ".{}.tmp",
+        path.file_name().and_then(|n| n.to_str()).unwrap_or("settings.json")
+    );
+    let tmp_path = parent.join(tmp_name);
+
+    let mut tmp_file = File::create(&tmp_path)?;
+    tmp_file.write_all(contents.as_bytes())?;
+    tmp_file.sync_all()?;
+    drop(tmp_file);
+
+    fs::rename(&tmp_path, path)?;
+    Ok(())
+}
+
+fn lock_path_for(settings_path: &Path) -> PathBuf {
+    let mut lock_path = settings_path.as_os_str().to_owned();
+    lock_path.push(".lock");
+    PathBuf::from(lock_path)
+}
+
+fn lock_is_stale(lock_path: &Path) -> bool {
+    fs::metadata(lock_path)
+        .and_then(|m| m.modified())
+        .map(|modified| modified.elapsed().unwrap_or_default() > STALE_LOCK_AGE)
+        .unwrap_or(false)
+}
+
+/// A token written into the lock file on acquire, unique enough across
+/// processes and threads that `Drop` can tell whether the lock file it's
+/// about to delete is still the one it created.
+fn unique_token() -> String {
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+    let counter = COUNTER.fetch_add(1, Ordering::Relaxed);
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos();
+    format!("{}-{nanos}-{counter}", std::process::id())
+}
+
+/// Holds an advisory lock on `<settings path>.lock`, releasing it on drop.
+///
+/// The lock file's content is a token unique to this guard. A lock file
+/// older than `STALE_LOCK_AGE` is assumed to be left behind by a crashed
+/// process and is reclaimed rather than waited on forever; `Drop` re-reads
+/// the file and only removes it if its token still matches, so a guard
+/// whose lock was reclaimed out from under it (or that outlived its own
+/// stale-reclaim window) can't delete a lock it no longer owns.
+pub struct SettingsGuard {
+    lock_path: PathBuf,
+    token: String,
+}
+
+impl SettingsGuard {
+    /// Acquire the lock for `settings_path`, waiting for a concurrent
+    /// holder to release it (or for its lock file to go stale).
+    pub fn acquire(settings_path: &Path) -> Result<Self> {
+        let lock_path = lock_path_for(settings_path);
+        if let Some(parent) = lock_path.parent() {
+            if !parent.as_os_str().is_empty() {
+                fs::create_dir_all(parent)?;
+            }
+        }
+
+        let token = unique_token();
+        let started = Instant::now();
+        loop {
+            match OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .open(&lock_path)
+            {
+                Ok(mut file) => {
+                    file.write_all(token.as_bytes())?;
+                    file.sync_all()?;
+                    return Ok(Self { lock_path, token });
+                }
+                Err(e) if e.kind() == io::ErrorKind::AlreadyExists => {
+                    if lock_is_stale(&lock_path) {
+                        let _ = fs::remove_file(&lock_path);
+                        continue;
+                    }
+                    if started.elapsed() > LOCK_WAIT_TIMEOUT {
+                        return Err(SilmariError::Io(io::Error::new(
+                            io::ErrorKind::TimedOut,
+                            format!("timed out waiting for lock {}", lock_path.display()),
+                        )));
+                    }
+                    thread::sleep(LOCK_POLL_INTERVAL);
+                }
+                Err(e) => return Err(SilmariError::Io(e)),
+            }
+        }
+    }
+}
+
+impl Drop for SettingsGuard {
+    fn drop(&mut self) {
+        let mut contents = String::new();
+        let owned_by_us = File::open(&self.lock_path)
+            .and_then(|mut f| f.read_to_string(&mut contents))
+            .map(|_| contents == self.token)
+            .unwrap_or(false);
+        if owned_by_us {
+            let _ = fs::remove_file(&self.lock_path);
+        }
+        // If the token doesn't match (or the file is gone), some other
+        // process has since reclaimed or released this lock — leave it
+        // alone rather than releasing a lock we no longer own.
+    }
+}
+
+impl super::ClaudeSettings {
+    /// Load, modify, and atomically save the settings at `path`, holding
+    /// the advisory lock for the whole cycle so a concurrent edit (e.g.
+    /// Claude Code toggling an unrelated setting) can't be lost to a race.
+    pub fn update_in_place(path: &Path, f: impl FnOnce(&mut Self)) -> Result<()> {
+        let _guard = SettingsGuard::acquire(path)?;
+        let mut settings = Self::load_from_path(path)?;
+        f(&mut settings);
+        settings.save_to_path(path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hooks::ClaudeSettings;
+    use tempfile::TempDir;
+
+    #[test]
+    fn atomic_write_never_leaves_a_stray_temp_file() {
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join("settings.json");
+
+        atomic_write(&path, "{}").unwrap();
+
+        assert_eq!(fs::read_to_string(&path).unwrap(), "{}");
+        let tmp_path = temp.path().join(".settings.json.tmp");
+        assert!(!tmp_path.exists());
+    }
+
+    #[test]
+    fn guard_acquire_then_release_allows_a_second_acquire() {
+        let temp = TempDir::new().unwrap();
+        let settings_path = temp.path().join("settings.json");
+
+        let guard = SettingsGuard::acquire(&settings_path).unwrap();
+        drop(guard);
+
+        // Should succeed immediately now that the lock file is gone.
+        let guard = SettingsGuard::acquire(&settings_path).unwrap();
+        drop(guard);
+        assert!(!lock_path_for(&settings_path).exists());
+    }
+
+    #[test]
+    fn drop_does_not_release_a_lock_reclaimed_by_another_holder() {
+        let temp = TempDir::new().unwrap();
+        let settings_path = temp.path().join("settings.json");
+        let lock_path = lock_path_for(&settings_path);
+
+        let original = SettingsGuard::acquire(&settings_path).unwrap();
+
+        // Backdate the lock file so a concurrent acquire treats it as
+        // abandoned, even though `original` is still alive — this is the
+        // scenario that used to let a waiter steal a live lock.
+        let backdated = SystemTime::now() - STALE_LOCK_AGE - Duration::from_secs(1);
+        OpenOptions::new()
+            .write(true)
+            .open(&lock_path)
+            .unwrap()
+            .set_modified(backdated)
+            .unwrap();
+
+        let stolen = SettingsGuard::acquire(&settings_path).unwrap();
+        assert_ne!(original.token, stolen.token);
+
+        drop(original);
+
+        // The file must still exist and still belong to `stolen` — the
+        // original guard's Drop must not have deleted it.
+        let contents = fs::read_to_string(&lock_path).unwrap();
+        assert_eq!(contents, stolen.token);
+
+        drop(stolen);
+        assert!(!lock_path.exists());
+    }
+
+    #[test]
+    fn update_in_place_applies_closure_and_persists_it() {
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join("settings.json");
+
+        ClaudeSettings::update_in_place(&path, |settings| {
+            settings.ensure_transcript_hook();
+        })
+        .unwrap();
+
+        let loaded = ClaudeSettings::load_from_path(&path).unwrap();
+        assert!(loaded.has_transcript_hook());
+        assert!(!lock_path_for(&path).exists());
+    }
+}
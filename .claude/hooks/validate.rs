@@ -0,0 +1,375 @@
+//! Pre-save validation for Claude Code settings
+//!
+//! `ClaudeSettings::save_to_path` stays lenient so callers can round-trip
+//! settings files we don't fully understand. `validate` is a stricter,
+//! opt-in pass (wired up via `save_validated`) that catches mistakes before
+//! they're written to disk: unrecognized hook types, out-of-range timeouts,
+//! matchers missing the `tools` list they need (or carrying one they
+//! shouldn't), and permission rules that don't parse into a known tool.
+
+use super::{HookEventAccessor, HookMatcher, Hooks, Permissions, PermissionRule};
+use std::path::Path;
+
+/// A single validation failure, with enough structure for a CLI or editor
+/// integration to point at the exact offending value.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ValidationError {
+    /// Machine-readable error code, e.g. `"unknown-hook-type"`.
+    pub code: &'static str,
+    /// JSON-pointer-style path to the offending value, e.g.
+    /// `hooks.PreToolUse[0].hooks[1].timeout`.
+    pub path: String,
+    /// Human-readable description of the problem.
+    pub message: String,
+}
+
+impl ValidationError {
+    fn new(code: &'static str, path: impl Into<String>, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            path: path.into(),
+            message: message.into(),
+        }
+    }
+}
+
+const KNOWN_HOOK_TYPES: &[&str] = &["command"];
+const MIN_TIMEOUT_SECS: u32 = 1;
+const MAX_TIMEOUT_SECS: u32 = 600;
+
+/// Tools Claude Code ships permission rules for today. Kept separate from
+/// any hook-matcher tool list since permission rules and hook matchers are
+/// validated against different grammars. MCP tools (`mcp__server__tool`)
+/// aren't listed here since their names are server-defined; they're
+/// accepted by prefix in `is_known_tool` instead.
+const KNOWN_TOOLS: &[&str] = &[
+    "Bash",
+    "BashOutput",
+    "KillShell",
+    "Read",
+    "Write",
+    "Edit",
+    "Glob",
+    "Grep",
+    "Task",
+    "WebFetch",
+    "WebSearch",
+    "NotebookEdit",
+    "NotebookRead",
+    "TodoWrite",
+    "ExitPlanMode",
+];
+
+const MCP_TOOL_PREFIX: &str = "mcp__";
+
+fn is_known_tool(tool: &str) -> bool {
+    KNOWN_TOOLS.contains(&tool) || tool.starts_with(MCP_TOOL_PREFIX)
+}
+
+/// Event names that fire per-tool and therefore require a `tools` list on
+/// each matcher.
+const TOOL_EVENTS: &[(&str, HookEventAccessor)] =
+    &[("PreToolUse", |h| &h.pre_tool_use), ("PostToolUse", |h| &h.post_tool_use)];
+
+/// Event names that fire once per turn/session and must not carry a `tools`
+/// list, since there's no tool invocation to filter by.
+const NON_TOOL_EVENTS: &[(&str, HookEventAccessor)] =
+    &[("Stop", |h| &h.stop), ("SubagentStop", |h| &h.subagent_stop)];
+
+fn validate_matchers(
+    event: &str,
+    matchers: &[HookMatcher],
+    requires_tools: bool,
+    errors: &mut Vec<ValidationError>,
+) {
+    for (mi, matcher) in matchers.iter().enumerate() {
+        let matcher_path = format!("hooks.{event}[{mi}]");
+
+        match (requires_tools, &matcher.matcher.tools) {
+            (true, None) => errors.push(ValidationError::new(
+                "missing-tool-matcher",
+                format!("{matcher_path}.matcher.tools"),
+                format!("{event} matchers must specify a `tools` list"),
+            )),
+            (false, Some(_)) => errors.push(ValidationError::new(
+                "unexpected-tool-matcher",
+                format!("{matcher_path}.matcher.tools"),
+                format!("{event} matchers must not specify `tools`"),
+            )),
+            _ => {}
+        }
+
+        for (hi, hook) in matcher.hooks.iter().enumerate() {
+            let hook_path = format!("{matcher_path}.hooks[{hi}]");
+
+            if !KNOWN_HOOK_TYPES.contains(&hook.hook_type.as_str()) {
+                errors.push(ValidationError::new(
+                    "unknown-hook-type",
+                    format!("{hook_path}.type"),
+                    format!("unrecognized hook type `{}`", hook.hook_type),
+                ));
+            }
+
+            if hook.command.trim().is_empty() {
+                errors.push(ValidationError::new(
+                    "empty-command",
+                    format!("{hook_path}.command"),
+                    "hook command must not be empty",
+                ));
+            }
+
+            if let Some(timeout) = hook.timeout {
+                if !(MIN_TIMEOUT_SECS..=MAX_TIMEOUT_SECS).contains(&timeout) {
+                    errors.push(ValidationError::new(
+                        "timeout-out-of-range",
+                        format!("{hook_path}.timeout"),
+                        format!(
+                            "timeout {timeout}s must be between {MIN_TIMEOUT_SECS}s and {MAX_TIMEOUT_SECS}s"
+                        ),
+                    ));
+                }
+            }
+        }
+    }
+}
+
+fn validate_hooks(hooks: &Hooks, errors: &mut Vec<ValidationError>) {
+    for (event, accessor) in TOOL_EVENTS {
+        if let Some(matchers) = accessor(hooks) {
+            validate_matchers(event, matchers, true, errors);
+        }
+    }
+    for (event, accessor) in NON_TOOL_EVENTS {
+        if let Some(matchers) = accessor(hooks) {
+            validate_matchers(event, matchers, false, errors);
+        }
+    }
+    // UserPromptSubmit and PreCompact aren't tied to a tool invocation, but
+    // they're also not exclusive like Stop/SubagentStop, so neither shape
+    // is enforced for them.
+}
+
+fn validate_permission_rules(permissions: &Permissions, errors: &mut Vec<ValidationError>) {
+    for (list_name, rules) in [
+        ("allow", &permissions.allow),
+        ("deny", &permissions.deny),
+        ("ask", &permissions.ask),
+    ] {
+        let Some(rules) = rules else { continue };
+        for (i, rule) in rules.iter().enumerate() {
+            let tool = PermissionRule::parse(rule).tool;
+            if !is_known_tool(&tool) {
+                errors.push(ValidationError::new(
+                    "unknown-permission-tool",
+                    format!("permissions.{list_name}[{i}]"),
+                    format!("`{rule}` does not name a recognized tool (got `{tool}`)"),
+                ));
+            }
+        }
+    }
+}
+
+impl super::ClaudeSettings {
+    /// Validate this settings value, returning every problem found rather
+    /// than stopping at the first one.
+    pub fn validate(&self) -> Result<(), Vec<ValidationError>> {
+        let mut errors = Vec::new();
+
+        if let Some(hooks) = &self.hooks {
+            validate_hooks(hooks, &mut errors);
+        }
+        if let Some(permissions) = &self.permissions {
+            validate_permission_rules(permissions, &mut errors);
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
+    /// Like `save_to_path`, but refuses to write if `validate` finds any
+    /// problems.
+    pub fn save_validated(&self, path: &Path) -> crate::error::Result<()> {
+        if let Err(errors) = self.validate() {
+            let summary = errors
+                .iter()
+                .map(|e| format!("{} ({}): {}", e.path, e.code, e.message))
+                .collect::<Vec<_>>()
+                .join("; ");
+            return Err(crate::error::SilmariError::Json(serde_json::Error::io(
+                std::io::Error::new(
+                    std::io::ErrorKind::InvalidInput,
+                    format!("settings failed validation: {summary}"),
+                ),
+            )));
+        }
+        self.save_to_path(path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hooks::{ClaudeSettings, HookAction, MatcherConfig};
+    use tempfile::TempDir;
+
+    fn error_codes(errors: &[ValidationError]) -> Vec<&str> {
+        errors.iter().map(|e| e.code).collect()
+    }
+
+    #[test]
+    fn valid_settings_pass() {
+        let json = r#"{
+            "permissions": {"allow": ["Bash(git commit:*)"]},
+            "hooks": {
+                "PreToolUse": [{
+                    "matcher": {"tools": ["Bash"]},
+                    "hooks": [{"type": "command", "command": "echo pre", "timeout": 30}]
+                }],
+                "Stop": [{
+                    "matcher": {},
+                    "hooks": [{"type": "command", "command": "echo stop"}]
+                }]
+            }
+        }"#;
+        let settings: ClaudeSettings = serde_json::from_str(json).unwrap();
+        assert_eq!(settings.validate(), Ok(()));
+    }
+
+    #[test]
+    fn rejects_unknown_hook_type() {
+        let matcher_config = MatcherConfig::default();
+        let mut hooks = Hooks::default();
+        hooks.stop = Some(vec![HookMatcher {
+            matcher: matcher_config,
+            hooks: vec![HookAction {
+                hook_type: "script".to_string(),
+                command: "echo hi".to_string(),
+                timeout: None,
+                extra: Default::default(),
+            }],
+        }]);
+        let mut settings = ClaudeSettings::default();
+        settings.hooks = Some(hooks);
+
+        let errors = settings.validate().unwrap_err();
+        assert_eq!(error_codes(&errors), vec!["unknown-hook-type"]);
+        assert_eq!(errors[0].path, "hooks.Stop[0].hooks[0].type");
+    }
+
+    #[test]
+    fn rejects_empty_command() {
+        let mut hooks = Hooks::default();
+        hooks.stop = Some(vec![HookMatcher {
+            matcher: MatcherConfig::default(),
+            hooks: vec![HookAction::command("   ")],
+        }]);
+        let mut settings = ClaudeSettings::default();
+        settings.hooks = Some(hooks);
+
+        let errors = settings.validate().unwrap_err();
+        assert!(error_codes(&errors).contains(&"empty-command"));
+    }
+
+    #[test]
+    fn rejects_timeout_out_of_range() {
+        let mut hooks = Hooks::default();
+        let mut action = HookAction::command("echo hi");
+        action.timeout = Some(0);
+        hooks.stop = Some(vec![HookMatcher {
+            matcher: MatcherConfig::default(),
+            hooks: vec![action],
+        }]);
+        let mut settings = ClaudeSettings::default();
+        settings.hooks = Some(hooks);
+
+        let errors = settings.validate().unwrap_err();
+        assert_eq!(error_codes(&errors), vec!["timeout-out-of-range"]);
+    }
+
+    #[test]
+    fn rejects_pre_tool_use_matcher_missing_tools() {
+        let mut hooks = Hooks::default();
+        hooks.pre_tool_use = Some(vec![HookMatcher {
+            matcher: MatcherConfig::default(),
+            hooks: vec![HookAction::command("echo hi")],
+        }]);
+        let mut settings = ClaudeSettings::default();
+        settings.hooks = Some(hooks);
+
+        let errors = settings.validate().unwrap_err();
+        assert_eq!(error_codes(&errors), vec!["missing-tool-matcher"]);
+        assert_eq!(errors[0].path, "hooks.PreToolUse[0].matcher.tools");
+    }
+
+    #[test]
+    fn rejects_stop_matcher_with_tools() {
+        let mut hooks = Hooks::default();
+        hooks.stop = Some(vec![HookMatcher {
+            matcher: MatcherConfig {
+                tools: Some(vec!["Bash".to_string()]),
+                extra: Default::default(),
+            },
+            hooks: vec![HookAction::command("echo hi")],
+        }]);
+        let mut settings = ClaudeSettings::default();
+        settings.hooks = Some(hooks);
+
+        let errors = settings.validate().unwrap_err();
+        assert_eq!(error_codes(&errors), vec!["unexpected-tool-matcher"]);
+    }
+
+    #[test]
+    fn rejects_unknown_permission_tool() {
+        let mut settings = ClaudeSettings::default();
+        settings.permissions = Some(Permissions {
+            allow: Some(vec!["Frobnicate(*)".to_string()]),
+            deny: None,
+            ask: None,
+            extra: Default::default(),
+        });
+
+        let errors = settings.validate().unwrap_err();
+        assert_eq!(error_codes(&errors), vec!["unknown-permission-tool"]);
+        assert_eq!(errors[0].path, "permissions.allow[0]");
+    }
+
+    #[test]
+    fn accepts_recently_added_builtin_tools_and_mcp_tools() {
+        let mut settings = ClaudeSettings::default();
+        settings.permissions = Some(Permissions {
+            allow: Some(vec![
+                "TodoWrite".to_string(),
+                "NotebookRead".to_string(),
+                "BashOutput".to_string(),
+                "KillShell".to_string(),
+                "ExitPlanMode".to_string(),
+                "mcp__github__create_issue".to_string(),
+            ]),
+            deny: None,
+            ask: None,
+            extra: Default::default(),
+        });
+
+        assert_eq!(settings.validate(), Ok(()));
+    }
+
+    #[test]
+    fn save_validated_refuses_to_write_invalid_settings() {
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join("settings.json");
+
+        let mut settings = ClaudeSettings::default();
+        settings.permissions = Some(Permissions {
+            allow: Some(vec!["Frobnicate(*)".to_string()]),
+            deny: None,
+            ask: None,
+            extra: Default::default(),
+        });
+
+        assert!(settings.save_validated(&path).is_err());
+        assert!(!path.exists());
+    }
+}